@@ -1,19 +1,41 @@
-use log::{error, warn};
-use semver::Version;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use log::{error, info, warn};
+use semver::{Version, VersionReq};
 
 use super::{Extension, ExtensionID, Metadata};
-use crate::models::common::UniqueID;
+use crate::models::common::{ClassificationID, DeviceID, ManufacturerID, UniqueID};
+
+/// The reason the manager refused to stage an extension.
+pub enum StageConflictReason {
+    /// The extension requires a newer inventory schema version than the running binary supports.
+    IncompatibleSchema {
+        required: Version,
+        current: Version,
+    },
+}
 
 /// Indicator that the manager encountered an error when staging an extension.
 pub struct StageConflict {
-    #[allow(dead_code)]
     id: ExtensionID,
+    reason: StageConflictReason,
 }
 
 impl StageConflict {
-    pub fn new(metadata: &Metadata) -> Self {
-        Self {
-            id: metadata.id.clone(),
+    pub(super) fn new(id: ExtensionID, reason: StageConflictReason) -> Self {
+        Self { id, reason }
+    }
+
+    /// Logs the appropriate message for a staging conflict.
+    pub(super) fn log(&self) {
+        let id_string = self.id.unnamespaced();
+        match &self.reason {
+            StageConflictReason::IncompatibleSchema { required, current } => error!(
+                "Refusing to stage extension '{}' because it requires schema version v{} or newer \
+                but this binary provides schema version v{}.",
+                id_string, required, current
+            ),
         }
     }
 }
@@ -32,12 +54,239 @@ pub(super) struct VersionChange {
     pub(super) staged_version: Version,
 }
 
+/// Indicator that an extension could not be loaded because of its declared dependencies.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum DependencyConflict {
+    /// A declared dependency was missing entirely, or no available version of it satisfied the
+    /// requirement.
+    Unsatisfied {
+        dependency: ExtensionID,
+        requirement: VersionReq,
+    },
+    /// The extension participates in or depends on a dependency cycle; `members` lists every
+    /// extension left unordered because of it.
+    Cycle { members: Vec<ExtensionID> },
+}
+
+/// Indicator that an extension could not be loaded because it was declared mutually incompatible
+/// with another extension that is already present.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct IncompatibilityConflict {
+    pub(super) conflicting: ExtensionID,
+}
+
 /// Indicator that the manager encountered an error when loading an extension.
 #[derive(Debug, PartialEq, Eq)]
 pub struct LoadConflict {
     pub(super) id: ExtensionID,
     pub(super) name_change: Option<NameChange>,
     pub(super) version_change: Option<VersionChange>,
+    pub(super) dependency: Option<DependencyConflict>,
+    pub(super) incompatibility: Option<IncompatibilityConflict>,
+    pub(super) compatibility: Option<CompatibilityReport>,
+    pub(super) content: Option<ContentDiff>,
+}
+
+/// The difference between the contents of a staged extension and the loaded version of the same
+/// extension, broken down by category. Unlike [`ExtensionDiff`], which only compares metadata,
+/// this captures exactly which manufacturers, classifications, and devices a reload would change.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct ContentDiff {
+    pub(super) manufacturers: CategoryDiff<ManufacturerID>,
+    pub(super) classifications: CategoryDiff<ClassificationID>,
+    pub(super) devices: CategoryDiff<DeviceID>,
+}
+
+/// The added, removed, and modified IDs of a single content category between two extension
+/// versions. *Added* IDs are present only in the staged version, *removed* only in the loaded
+/// version, and *modified* in both but with differing fields.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct CategoryDiff<I> {
+    pub(super) added: Vec<I>,
+    pub(super) removed: Vec<I>,
+    pub(super) modified: Vec<I>,
+}
+
+impl<I> Default for CategoryDiff<I> {
+    fn default() -> Self {
+        Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            modified: Vec::new(),
+        }
+    }
+}
+
+impl ContentDiff {
+    /// Computes the content diff between a staged extension and the currently loaded version of the
+    /// same extension.
+    pub(super) fn new(staged: &Extension, loaded: &Extension) -> Self {
+        ContentDiff {
+            manufacturers: diff_category(
+                &staged.manufacturers,
+                &loaded.manufacturers,
+                |m| m.id.clone(),
+                |s, l| s.common_name == l.common_name,
+            ),
+            classifications: diff_category(
+                &staged.classifications,
+                &loaded.classifications,
+                |c| c.id.clone(),
+                |s, l| s.common_name == l.common_name,
+            ),
+            devices: diff_category(
+                &staged.devices,
+                &loaded.devices,
+                |d| d.id.clone(),
+                |s, l| {
+                    s.common_name == l.common_name
+                        && s.manufacturer == l.manufacturer
+                        && s.classification == l.classification
+                        && s.primary_model_identifiers == l.primary_model_identifiers
+                        && s.extended_model_identifiers == l.extended_model_identifiers
+                },
+            ),
+        }
+    }
+
+    /// Logs a concise, per-category summary (counts plus affected IDs) of the content diff.
+    pub(super) fn log(&self, id_string: &str) {
+        info!(
+            "Content changes for extension '{}' - manufacturers {}, classifications {}, devices \
+            {}.",
+            id_string,
+            self.manufacturers.summary(),
+            self.classifications.summary(),
+            self.devices.summary()
+        );
+    }
+}
+
+impl<I: UniqueID> CategoryDiff<I> {
+    /// Renders the category's counts and affected IDs as a single summary fragment.
+    fn summary(&self) -> String {
+        let join = |ids: &[I]| {
+            ids.iter()
+                .map(UniqueID::unnamespaced)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        format!(
+            "+{} -{} ~{} (added: [{}], removed: [{}], modified: [{}])",
+            self.added.len(),
+            self.removed.len(),
+            self.modified.len(),
+            join(&self.added),
+            join(&self.removed),
+            join(&self.modified)
+        )
+    }
+}
+
+/// Diffs two slices of records by ID, classifying each into added, removed, or modified using the
+/// provided ID extractor and field-equality predicate.
+fn diff_category<T, I>(
+    staged: &[T],
+    loaded: &[T],
+    id_of: impl Fn(&T) -> I,
+    same_fields: impl Fn(&T, &T) -> bool,
+) -> CategoryDiff<I>
+where
+    I: Clone + Eq + Hash,
+{
+    let loaded_by_id: HashMap<I, &T> = loaded.iter().map(|x| (id_of(x), x)).collect();
+    let staged_by_id: HashMap<I, &T> = staged.iter().map(|x| (id_of(x), x)).collect();
+
+    let mut diff = CategoryDiff::default();
+    for staged_record in staged {
+        let id = id_of(staged_record);
+        match loaded_by_id.get(&id) {
+            None => diff.added.push(id),
+            Some(loaded_record) if !same_fields(staged_record, loaded_record) => {
+                diff.modified.push(id)
+            }
+            Some(_) => {}
+        }
+    }
+
+    for loaded_record in loaded {
+        let id = id_of(loaded_record);
+        if !staged_by_id.contains_key(&id) {
+            diff.removed.push(id);
+        }
+    }
+
+    diff
+}
+
+/// A classification of the change between a loaded extension and a newer staged version of it,
+/// used to guard against a version bump orphaning live inventory records.
+///
+/// A change is *breaking* when the staged version removes or renames a manufacturer,
+/// classification, or device ID which still exists in the loaded version, since inventory rows may
+/// reference it. Purely additive changes (new devices, new model identifiers) are compatible.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct CompatibilityReport {
+    pub(super) removed_manufacturers: Vec<ManufacturerID>,
+    pub(super) removed_classifications: Vec<ClassificationID>,
+    pub(super) removed_devices: Vec<DeviceID>,
+}
+
+impl CompatibilityReport {
+    /// Derives the compatibility report from an already-computed [`ContentDiff`]. The breaking
+    /// changes are exactly the IDs the diff reports as removed, so there is no need to walk the
+    /// extension contents a second time.
+    pub(super) fn from_content_diff(diff: &ContentDiff) -> Self {
+        CompatibilityReport {
+            removed_manufacturers: diff.manufacturers.removed.clone(),
+            removed_classifications: diff.classifications.removed.clone(),
+            removed_devices: diff.devices.removed.clone(),
+        }
+    }
+
+    /// Checks whether the report contains any breaking (ID-removing) change.
+    pub(super) fn is_breaking(&self) -> bool {
+        !self.removed_manufacturers.is_empty()
+            || !self.removed_classifications.is_empty()
+            || !self.removed_devices.is_empty()
+    }
+
+    /// Logs a summary of the removed IDs so an operator can see what a reload would break.
+    pub(super) fn log(&self) {
+        if !self.is_breaking() {
+            return;
+        }
+
+        let format_ids = |ids: &[String]| ids.join(", ");
+        let manufacturers = format_ids(
+            &self
+                .removed_manufacturers
+                .iter()
+                .map(UniqueID::unnamespaced)
+                .collect::<Vec<_>>(),
+        );
+        let classifications = format_ids(
+            &self
+                .removed_classifications
+                .iter()
+                .map(UniqueID::unnamespaced)
+                .collect::<Vec<_>>(),
+        );
+        let devices = format_ids(
+            &self
+                .removed_devices
+                .iter()
+                .map(UniqueID::unnamespaced)
+                .collect::<Vec<_>>(),
+        );
+
+        warn!(
+            "Reload contains breaking changes - removed manufacturers: [{}], classifications: \
+            [{}], devices: [{}].",
+            manufacturers, classifications, devices
+        );
+    }
 }
 
 impl LoadConflict {
@@ -81,6 +330,10 @@ impl LoadConflict {
                 } else {
                     None
                 },
+                dependency: None,
+                incompatibility: None,
+                compatibility: None,
+                content: None,
             };
 
             // Skip the conflicting extension in subsequent conflict checks for optimization.
@@ -91,10 +344,93 @@ impl LoadConflict {
         None
     }
 
+    /// Constructs a conflict for a staged extension which cannot be loaded because one of its
+    /// declared dependencies is unsatisfiable, or because it is part of or depends on a dependency
+    /// cycle.
+    pub(super) fn unsatisfied_dependency(
+        metadata: &Metadata,
+        dependency: DependencyConflict,
+    ) -> Self {
+        LoadConflict {
+            id: metadata.id.clone(),
+            name_change: None,
+            version_change: None,
+            dependency: Some(dependency),
+            incompatibility: None,
+            compatibility: None,
+            content: None,
+        }
+    }
+
+    /// Constructs a conflict for a staged extension which cannot be loaded because it is mutually
+    /// incompatible with another extension that is already present.
+    pub(super) fn incompatibility(
+        metadata: &Metadata,
+        incompatibility: IncompatibilityConflict,
+    ) -> Self {
+        LoadConflict {
+            id: metadata.id.clone(),
+            name_change: None,
+            version_change: None,
+            dependency: None,
+            incompatibility: Some(incompatibility),
+            compatibility: None,
+            content: None,
+        }
+    }
+
+    /// Attaches a compatibility report describing the content changes of a pending reload.
+    pub(super) fn attach_compatibility(&mut self, report: CompatibilityReport) {
+        self.compatibility = Some(report);
+    }
+
+    /// Attaches a structured content diff describing what a pending reload would change.
+    pub(super) fn attach_content(&mut self, diff: ContentDiff) {
+        self.content = Some(diff);
+    }
+
     /// Logs the appropriate message for a conflict.
     pub(super) fn log(&self, auto_handle: bool) {
         let id_string = self.id.unnamespaced();
 
+        if let Some(dependency) = &self.dependency {
+            match dependency {
+                DependencyConflict::Unsatisfied {
+                    dependency,
+                    requirement,
+                } => error!(
+                    "Skipping extension '{}' because its dependency '{}' could not be satisfied \
+                    (requires '{}').",
+                    id_string,
+                    dependency.unnamespaced(),
+                    requirement
+                ),
+                DependencyConflict::Cycle { members } => {
+                    let members = members
+                        .iter()
+                        .map(UniqueID::unnamespaced)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    error!(
+                        "Skipping extension '{}' because it is part of or depends on a dependency \
+                        cycle ({}).",
+                        id_string, members
+                    );
+                }
+            }
+            return;
+        }
+
+        if let Some(incompatibility) = &self.incompatibility {
+            error!(
+                "Skipping extension '{}' because it is mutually incompatible with the already \
+                present extension '{}'.",
+                id_string,
+                incompatibility.conflicting.unnamespaced()
+            );
+            return;
+        }
+
         if let Some(name_change) = &self.name_change {
             warn!(
                 "Loaded and staged extension with ID '{}' have conflicting display names '{}' and \
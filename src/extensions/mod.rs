@@ -2,40 +2,63 @@ mod conflicts;
 #[cfg(test)]
 mod tests;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fs::DirEntry;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use log::{error, info};
-use semver::Version;
+use semver::{Version, VersionReq};
 use serde::Deserialize;
 
-use self::conflicts::LoadConflict;
+use self::conflicts::{
+    CompatibilityReport, ContentDiff, DependencyConflict, IncompatibilityConflict, LoadConflict,
+    StageConflict, StageConflictReason,
+};
 use crate::database::Database;
 use crate::models::common::{
     Classification, ClassificationID, Device, DeviceID, InventoryExtensionID as ExtensionID,
     InventoryExtensionMetadata as Metadata, Manufacturer, ManufacturerID,
 };
 
+/// The inventory schema version supported by this build. Extensions whose `requires_schema`
+/// requirement does not admit this version are rejected during staging, so that older binaries
+/// cleanly decline extensions authored against future schema features.
+pub const CURRENT_SCHEMA_VERSION: Version = Version::new(1, 0, 0);
+
 /// An extension of the database inventory system.
 #[derive(Debug, Clone)]
 pub struct InventoryExtension {
     pub metadata: Metadata,
+    pub dependencies: Vec<ExtensionDependency>,
+    pub incompatibilities: Vec<ExtensionID>,
     pub manufacturers: Vec<Manufacturer>,
     pub classifications: Vec<Classification>,
     pub devices: Vec<Device>,
 }
 
+/// A dependency of one extension upon another, resolved from the `dependencies` table of a TOML
+/// extension. The requirement is tested against the highest available version of the depended-on
+/// extension when determining the load order.
+#[derive(Debug, Clone)]
+pub struct ExtensionDependency {
+    pub id: ExtensionID,
+    pub requirement: VersionReq,
+}
+
 /// An inventory extension as read from a TOML file.
 /// Some types are not compatible with the database, so this type must be converted into an
 /// [`InventoryExtension`] before calling [`Database::load_extension`].
 #[derive(Debug, Deserialize)]
-struct InventoryExtensionToml {
+pub struct InventoryExtensionToml {
     extension_id: String,
     extension_common_name: String,
     extension_version: String,
+    requires_schema: Option<String>,
+    dependencies: Option<HashMap<String, String>>,
+    conflicts: Option<Vec<String>>,
     manufacturers: Vec<ManufacturerToml>,
     classifications: Option<Vec<ClassificationToml>>,
     devices: Vec<DeviceToml>,
@@ -70,10 +93,74 @@ pub struct DeviceToml {
     extended_model_identifiers: Vec<String>,
 }
 
+/// Deserializes the raw bytes of an extension file of a particular format into the intermediate
+/// [`InventoryExtensionToml`] representation. Downstream code can implement this trait and register
+/// it with [`ExtensionManager::register_loader`] to support additional authoring formats.
+pub trait ExtensionLoader: Send + Sync {
+    /// The file extensions (without the leading dot) this loader is responsible for.
+    fn extensions(&self) -> &[&str];
+    /// Deserializes the contents of an extension file into its intermediate representation.
+    fn load(&self, bytes: &[u8]) -> anyhow::Result<InventoryExtensionToml>;
+}
+
+/// The built-in loader for TOML extension files.
+struct TomlLoader;
+
+impl ExtensionLoader for TomlLoader {
+    fn extensions(&self) -> &[&str] {
+        &["toml"]
+    }
+
+    fn load(&self, bytes: &[u8]) -> anyhow::Result<InventoryExtensionToml> {
+        Ok(toml::from_str(std::str::from_utf8(bytes)?)?)
+    }
+}
+
+/// The built-in loader for JSON extension files.
+struct JsonLoader;
+
+impl ExtensionLoader for JsonLoader {
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+
+    fn load(&self, bytes: &[u8]) -> anyhow::Result<InventoryExtensionToml> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// The built-in loader for YAML extension files.
+struct YamlLoader;
+
+impl ExtensionLoader for YamlLoader {
+    fn extensions(&self) -> &[&str] {
+        &["yaml", "yml"]
+    }
+
+    fn load(&self, bytes: &[u8]) -> anyhow::Result<InventoryExtensionToml> {
+        Ok(serde_yaml::from_slice(bytes)?)
+    }
+}
+
 /// Manages the parsing and loading of extensions into the database.
-#[derive(Default)]
 pub struct ExtensionManager {
     staged_extensions: Vec<InventoryExtension>,
+    loaders: HashMap<String, Arc<dyn ExtensionLoader>>,
+}
+
+impl Default for ExtensionManager {
+    fn default() -> Self {
+        let mut manager = Self {
+            staged_extensions: Vec::new(),
+            loaders: HashMap::new(),
+        };
+
+        manager.register_loader(Arc::new(TomlLoader));
+        manager.register_loader(Arc::new(JsonLoader));
+        manager.register_loader(Arc::new(YamlLoader));
+
+        manager
+    }
 }
 
 impl ExtensionManager {
@@ -81,7 +168,7 @@ impl ExtensionManager {
     pub fn new() -> anyhow::Result<Self> {
         let mut manager = Self::default();
         for extension_file in std::fs::read_dir("./extensions")?.flatten() {
-            if Self::is_extension(&extension_file) {
+            if manager.is_extension(&extension_file) {
                 info!(
                     "Located extension file: {}",
                     extension_file.path().display()
@@ -93,11 +180,59 @@ impl ExtensionManager {
         Ok(manager)
     }
 
-    /// Parses a TOML file into an extension which can be added to the database by the manager.
+    /// Registers a loader under each of the file extensions it supports, allowing the manager to
+    /// ingest extensions authored in that format. A later registration for a given file extension
+    /// overrides an earlier one.
+    pub fn register_loader(&mut self, loader: Arc<dyn ExtensionLoader>) {
+        for extension in loader.extensions() {
+            self.loaders
+                .insert((*extension).to_owned(), Arc::clone(&loader));
+        }
+    }
+
+    /// Parses an extension file into an extension which can be added to the database by the
+    /// manager, dispatching to the loader registered for the file's extension.
     fn stage_extension(&mut self, filename: &Path) -> anyhow::Result<()> {
-        let toml = std::fs::read_to_string(filename)?;
-        let extension_toml: InventoryExtensionToml = toml::from_str(&toml)?;
-        let extension = InventoryExtension::from(extension_toml);
+        let Some(loader) = filename
+            .extension()
+            .and_then(OsStr::to_str)
+            .and_then(|ext| self.loaders.get(ext))
+            .map(Arc::clone)
+        else {
+            anyhow::bail!(
+                "No extension loader is registered for file '{}'.",
+                filename.display()
+            );
+        };
+
+        let bytes = std::fs::read(filename)?;
+        let extension_toml = loader.load(&bytes)?;
+
+        // Reject extensions which demand a newer schema version than this binary supports before
+        // converting them, as their contents may rely on features this build cannot represent and
+        // could otherwise fail to convert. `requires_schema` is a floor: an extension loads when
+        // the running schema version is at least the one it was authored against, so newer binaries
+        // keep accepting older extensions. A missing requirement means "accept any".
+        let requires_schema = extension_toml
+            .requires_schema
+            .as_deref()
+            .map(Version::from_str)
+            .transpose()?;
+        if let Some(required) = requires_schema {
+            if CURRENT_SCHEMA_VERSION < required {
+                let conflict = StageConflict::new(
+                    ExtensionID::new(&extension_toml.extension_id),
+                    StageConflictReason::IncompatibleSchema {
+                        required,
+                        current: CURRENT_SCHEMA_VERSION,
+                    },
+                );
+                conflict.log();
+                return Ok(());
+            }
+        }
+
+        let extension = InventoryExtension::try_from(extension_toml)?;
         if !self.already_contains(&extension) {
             info!(
                 "Staging extension '{}'.",
@@ -139,11 +274,23 @@ impl ExtensionManager {
         let mut loaded_extensions = db.list_extensions().await?;
 
         let mut conflicts = Vec::new();
-        'current_extension: for staged_extension in self.staged_extensions.into_iter() {
+
+        // Reject any staged extension declared mutually incompatible with an extension that is
+        // already loaded or precedes it in the staged order. This runs first so that the dependency
+        // resolution below only considers extensions which will actually be present.
+        let staged_extensions =
+            Self::reject_incompatibilities(self.staged_extensions, &loaded_extensions, &mut conflicts);
+
+        // Resolve inter-extension dependencies, dropping any extension whose requirements cannot be
+        // satisfied and ordering the remainder so that each extension's dependencies load first.
+        let staged_extensions =
+            Self::resolve_load_order(staged_extensions, &loaded_extensions, &mut conflicts);
+
+        'current_extension: for staged_extension in staged_extensions.into_iter() {
             let staged_extension_metadata = &staged_extension.metadata;
             let staged_extension_id = staged_extension_metadata.id.to_non_namespaced_string();
 
-            let Some(conflict) = LoadConflict::new(&staged_extension, &mut loaded_extensions)
+            let Some(mut conflict) = LoadConflict::new(&staged_extension, &mut loaded_extensions)
             else {
                 info!("Loading extension '{}'.", &staged_extension_id);
                 db.load_extension(staged_extension).await?;
@@ -151,8 +298,32 @@ impl ExtensionManager {
             };
 
             conflict.log(load_override);
+
             if load_override || conflict.should_reload() {
-                db.reload_extension(staged_extension).await?;
+                // Diff the staged contents against the currently loaded contents so an operator can
+                // see exactly what a version bump will change, not just the version delta. The
+                // loaded contents are only fetched for reload candidates.
+                let loaded_extension = db.get_extension(&conflict.id).await?;
+                let content_diff = ContentDiff::new(&staged_extension, &loaded_extension);
+                content_diff.log(&staged_extension_id);
+
+                // A removed or renamed ID may still be referenced by live inventory records, so
+                // require the override before committing a breaking reload.
+                let report = CompatibilityReport::from_content_diff(&content_diff);
+                let breaking = report.is_breaking();
+                report.log();
+                conflict.attach_compatibility(report);
+                conflict.attach_content(content_diff);
+
+                if breaking && !load_override {
+                    error!(
+                        "Skipping reload of extension '{}' because it contains breaking changes; \
+                        pass the load override to proceed.",
+                        &staged_extension_id
+                    );
+                } else {
+                    db.reload_extension(staged_extension).await?;
+                }
             }
 
             conflicts.push(conflict);
@@ -161,12 +332,220 @@ impl ExtensionManager {
         Ok(conflicts)
     }
 
-    /// Checks whether a given filesystem object is a valid extension.
-    fn is_extension(object: &DirEntry) -> bool {
+    /// Resolves the inter-extension dependencies of the staged set, returning the extensions in an
+    /// order where every extension's dependencies precede it. Any extension whose requirements
+    /// cannot be satisfied by an available (staged or loaded) version, or which participates in or
+    /// depends on a dependency cycle, is dropped and recorded as a [`LoadConflict`]; independent extensions are
+    /// unaffected.
+    fn resolve_load_order(
+        staged: Vec<InventoryExtension>,
+        loaded: &[Metadata],
+        conflicts: &mut Vec<LoadConflict>,
+    ) -> Vec<InventoryExtension> {
+        // The highest version available for each extension ID among the already-loaded extensions.
+        // Staged versions are layered on top each round and recomputed against the survivors, so
+        // that an extension dropped for its own failure cannot still satisfy a dependent's
+        // requirement.
+        let mut loaded_available: HashMap<ExtensionID, Version> = HashMap::new();
+        for metadata in loaded {
+            Self::record_available(
+                &mut loaded_available,
+                metadata.id.clone(),
+                metadata.version.clone(),
+            );
+        }
+
+        // Repeatedly drop any extension with an unsatisfiable dependency until the surviving set is
+        // stable, cascading the removal to any dependent of a dropped extension.
+        let mut satisfied = staged;
+        loop {
+            let mut available = loaded_available.clone();
+            for extension in &satisfied {
+                let metadata = &extension.metadata;
+                Self::record_available(
+                    &mut available,
+                    metadata.id.clone(),
+                    metadata.version.clone(),
+                );
+            }
+
+            let mut kept = Vec::with_capacity(satisfied.len());
+            let mut dropped_any = false;
+            'next_extension: for extension in satisfied.into_iter() {
+                for dependency in &extension.dependencies {
+                    let satisfied_by = available
+                        .get(&dependency.id)
+                        .is_some_and(|version| dependency.requirement.matches(version));
+                    if !satisfied_by {
+                        let conflict = LoadConflict::unsatisfied_dependency(
+                            &extension.metadata,
+                            DependencyConflict::Unsatisfied {
+                                dependency: dependency.id.clone(),
+                                requirement: dependency.requirement.clone(),
+                            },
+                        );
+                        conflict.log(false);
+                        conflicts.push(conflict);
+                        dropped_any = true;
+                        continue 'next_extension;
+                    }
+                }
+                kept.push(extension);
+            }
+
+            satisfied = kept;
+            if !dropped_any {
+                break;
+            }
+        }
+
+        // Topologically sort the survivors via Kahn's algorithm so that dependencies load first.
+        let ids: HashSet<ExtensionID> =
+            satisfied.iter().map(|e| e.metadata.id.clone()).collect();
+        let mut in_degree: Vec<usize> = satisfied
+            .iter()
+            .map(|e| {
+                e.dependencies
+                    .iter()
+                    .filter(|d| ids.contains(&d.id))
+                    .count()
+            })
+            .collect();
+
+        let mut queue: Vec<usize> = in_degree
+            .iter()
+            .enumerate()
+            .filter_map(|(i, degree)| (*degree == 0).then_some(i))
+            .collect();
+
+        let mut order = Vec::with_capacity(satisfied.len());
+        let mut emitted = vec![false; satisfied.len()];
+        while let Some(node) = queue.pop() {
+            emitted[node] = true;
+            order.push(node);
+            // Dropping this node to zero in-degree may unblock its dependents.
+            for (dependent, extension) in satisfied.iter().enumerate() {
+                if emitted[dependent] {
+                    continue;
+                }
+                let depends_on_node = extension
+                    .dependencies
+                    .iter()
+                    .any(|d| d.id == satisfied[node].metadata.id);
+                if depends_on_node {
+                    in_degree[dependent] -= 1;
+                    if in_degree[dependent] == 0 {
+                        queue.push(dependent);
+                    }
+                }
+            }
+        }
+
+        if order.len() < satisfied.len() {
+            // The unemitted nodes are part of, or depend on, one or more dependency cycles.
+            let members: Vec<ExtensionID> = satisfied
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !emitted[*i])
+                .map(|(_, e)| e.metadata.id.clone())
+                .collect();
+            for (i, extension) in satisfied.iter().enumerate() {
+                if emitted[i] {
+                    continue;
+                }
+                let conflict = LoadConflict::unsatisfied_dependency(
+                    &extension.metadata,
+                    DependencyConflict::Cycle {
+                        members: members.clone(),
+                    },
+                );
+                conflict.log(false);
+                conflicts.push(conflict);
+            }
+        }
+
+        // Reassemble the extensions in dependency order, consuming the sorted survivors.
+        let mut ordered_slots: Vec<Option<InventoryExtension>> =
+            satisfied.into_iter().map(Some).collect();
+        order
+            .into_iter()
+            .filter_map(|i| ordered_slots[i].take())
+            .collect()
+    }
+
+    /// Filters out any staged extension which declares an incompatibility with an extension that is
+    /// already loaded or accepted earlier in the staged order, or which an earlier accepted
+    /// extension declares incompatible with it. The later member of each incompatible pair is
+    /// dropped and recorded as a [`LoadConflict`].
+    fn reject_incompatibilities(
+        staged: Vec<InventoryExtension>,
+        loaded: &[Metadata],
+        conflicts: &mut Vec<LoadConflict>,
+    ) -> Vec<InventoryExtension> {
+        // The IDs already present, seeded with the loaded extensions, alongside the
+        // incompatibilities each accepted extension declares so the reverse direction is caught.
+        let mut present: HashSet<ExtensionID> =
+            loaded.iter().map(|m| m.id.clone()).collect();
+        let mut declared: Vec<(ExtensionID, Vec<ExtensionID>)> = Vec::new();
+
+        let mut accepted = Vec::with_capacity(staged.len());
+        for extension in staged.into_iter() {
+            let id = &extension.metadata.id;
+
+            let forward = extension
+                .incompatibilities
+                .iter()
+                .find(|other| present.contains(other));
+            let reverse = declared
+                .iter()
+                .find(|(_, incompatibilities)| incompatibilities.contains(id))
+                .map(|(other, _)| other);
+
+            if let Some(conflicting) = forward.or(reverse) {
+                let conflict = LoadConflict::incompatibility(
+                    &extension.metadata,
+                    IncompatibilityConflict {
+                        conflicting: conflicting.clone(),
+                    },
+                );
+                conflict.log(false);
+                conflicts.push(conflict);
+                continue;
+            }
+
+            present.insert(id.clone());
+            declared.push((id.clone(), extension.incompatibilities.clone()));
+            accepted.push(extension);
+        }
+
+        accepted
+    }
+
+    /// Records the highest known version for an extension ID in the availability map.
+    fn record_available(
+        available: &mut HashMap<ExtensionID, Version>,
+        id: ExtensionID,
+        version: Version,
+    ) {
+        available
+            .entry(id)
+            .and_modify(|existing| {
+                if version > *existing {
+                    *existing = version.clone();
+                }
+            })
+            .or_insert(version);
+    }
+
+    /// Checks whether a given filesystem object is a valid extension, i.e. a file whose extension
+    /// has a registered loader.
+    fn is_extension(&self, object: &DirEntry) -> bool {
         let (path, filetype) = (object.path(), object.file_type());
         if let Ok(filetype) = filetype {
-            if filetype.is_file() && path.extension() == Some(OsStr::new("toml")) {
-                return true;
+            if filetype.is_file() {
+                if let Some(extension) = path.extension().and_then(OsStr::to_str) {
+                    return self.loaders.contains_key(extension);
+                }
             }
         }
 
@@ -174,11 +553,35 @@ impl ExtensionManager {
     }
 }
 
-// TODO: Remove unwraps
 // * Inner types here ([`Manufacturer`], [`Classification`], [`Device`]) must be converted with
 // *  context provided by the [`ExtensionToml`] itself, so they cannot be converted directly.
-impl From<InventoryExtensionToml> for InventoryExtension {
-    fn from(toml: InventoryExtensionToml) -> Self {
+impl TryFrom<InventoryExtensionToml> for InventoryExtension {
+    type Error = anyhow::Error;
+
+    fn try_from(toml: InventoryExtensionToml) -> anyhow::Result<Self> {
+        // Version and requirement strings are user-authored, so malformed values are surfaced as
+        // handled errors rather than panicking mid-conversion.
+        let version = Version::from_str(&toml.extension_version)?;
+
+        let dependencies = toml
+            .dependencies
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, requirement)| {
+                Ok(ExtensionDependency {
+                    id: ExtensionID::new(&id),
+                    requirement: VersionReq::from_str(&requirement)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let incompatibilities = toml
+            .conflicts
+            .unwrap_or_default()
+            .iter()
+            .map(|id| ExtensionID::new(id))
+            .collect();
+
         let manufacturers = toml
             .manufacturers
             .into_iter()
@@ -220,15 +623,17 @@ impl From<InventoryExtensionToml> for InventoryExtension {
             })
             .collect();
 
-        InventoryExtension {
+        Ok(InventoryExtension {
             metadata: Metadata {
                 id: ExtensionID::new(&toml.extension_id),
                 common_name: toml.extension_common_name,
-                version: Version::from_str(&toml.extension_version).unwrap(),
+                version,
             },
+            dependencies,
+            incompatibilities,
             manufacturers,
             classifications,
             devices,
-        }
+        })
     }
 }
\ No newline at end of file